@@ -0,0 +1,338 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use gix::remote::Direction;
+use tokio::sync::Mutex;
+use typst::syntax::PackageSpec;
+
+use crate::workspace::package::manager::{ExternalPackageError, ExternalPackageResult};
+
+use super::RepoProvider;
+
+/// Serves packages out of a git repository, fetching it into a working
+/// directory under the cache and reading `<namespace>/<name>/<version>/`
+/// straight out of the resolved ref's tree.
+///
+/// The clone is bare (object database only, no worktree) and subsequent
+/// lookups reuse it, fetching only new commits on the configured ref rather
+/// than re-cloning. Reading package contents from the tree object itself,
+/// rather than from files checked out on disk, means a fetch that moves the
+/// ref is immediately reflected without a separate checkout step.
+#[derive(Debug)]
+pub struct GitRepoProvider {
+    url: String,
+    reference: String,
+    /// Guards the on-disk checkout directory for its *entire* clone/fetch
+    /// duration, so two specs resolving through this provider concurrently
+    /// (as `prefetch`'s bounded fan-out does) can't run `gix` operations
+    /// against it at the same time.
+    checkout: Mutex<PathBuf>,
+}
+
+impl GitRepoProvider {
+    /// `checkout` is the directory the repository is cloned into (and later
+    /// fetched in-place); `reference` defaults to `HEAD` when `None`.
+    pub fn new(url: String, reference: Option<String>, checkout: PathBuf) -> Self {
+        Self {
+            url,
+            reference: reference.unwrap_or_else(|| "HEAD".to_owned()),
+            checkout: Mutex::new(checkout),
+        }
+    }
+
+    /// Brings the local checkout up to date with `self.reference`, cloning
+    /// it fresh if it doesn't exist yet, and returns the repository together
+    /// with the `ObjectId` of the resolved commit's tree.
+    fn sync_tree(&self, dir: &Path) -> ExternalPackageResult<(gix::Repository, gix::ObjectId)> {
+        let repo = if dir.join("HEAD").is_file() {
+            self.fetch_existing(dir)?
+        } else {
+            self.clone_fresh(dir)?
+        };
+
+        let tree_id = self.resolve_tree_id(&repo)?;
+        Ok((repo, tree_id))
+    }
+
+    /// The refspec pinned on every fetch, mirroring every ref under
+    /// `refs/` rather than relying on whatever refspec the remote happens
+    /// to be configured with. `git clone --bare` (and `gix`'s equivalent)
+    /// doesn't always set one up the way a normal clone does, and a bare
+    /// repo with no fetch refspec silently pulls nothing on a later fetch —
+    /// leaving [`resolve_tree_id`](Self::resolve_tree_id) resolving
+    /// `self.reference` against a stale local ref even after the remote
+    /// moved.
+    const MIRROR_REFSPEC: &'static str = "+refs/*:refs/*";
+
+    fn clone_fresh(&self, dir: &Path) -> ExternalPackageResult<gix::Repository> {
+        std::fs::create_dir_all(dir).map_err(|err| ExternalPackageError::GitConnect {
+            url: self.url.clone(),
+            source: anyhow::Error::new(err).context("could not create checkout directory"),
+        })?;
+
+        let mut prepare = gix::prepare_clone_bare(self.url.as_str(), dir)
+            .and_then(|prepare| prepare.with_refspec(Self::MIRROR_REFSPEC, Direction::Fetch))
+            .map_err(|err| ExternalPackageError::GitConnect {
+                url: self.url.clone(),
+                source: err.into(),
+            })?;
+
+        let (repo, _) = prepare
+            .fetch_only(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
+            .map_err(|err| ExternalPackageError::GitFetch {
+                url: self.url.clone(),
+                source: err.into(),
+            })?;
+
+        Ok(repo)
+    }
+
+    fn fetch_existing(&self, dir: &Path) -> ExternalPackageResult<gix::Repository> {
+        let repo = gix::open(dir).map_err(|err| ExternalPackageError::GitConnect {
+            url: self.url.clone(),
+            source: err.into(),
+        })?;
+
+        let remote = repo
+            .find_default_remote(Direction::Fetch)
+            .ok_or_else(|| ExternalPackageError::GitConnect {
+                url: self.url.clone(),
+                source: anyhow::anyhow!("repository has no default remote"),
+            })?
+            .map_err(|err| ExternalPackageError::GitConnect {
+                url: self.url.clone(),
+                source: err.into(),
+            })?
+            .with_refspec(Self::MIRROR_REFSPEC, Direction::Fetch)
+            .map_err(|err| ExternalPackageError::GitConnect {
+                url: self.url.clone(),
+                source: err.into(),
+            })?;
+
+        remote
+            .connect(Direction::Fetch)
+            .map_err(|err| ExternalPackageError::GitConnect {
+                url: self.url.clone(),
+                source: err.into(),
+            })?
+            .prepare_fetch(gix::progress::Discard, Default::default())
+            .map_err(|err| ExternalPackageError::GitFetch {
+                url: self.url.clone(),
+                source: err.into(),
+            })?
+            .receive(gix::progress::Discard, &gix::interrupt::IS_INTERRUPTED)
+            .map_err(|err| ExternalPackageError::GitFetch {
+                url: self.url.clone(),
+                source: err.into(),
+            })?;
+
+        Ok(repo)
+    }
+
+    /// Resolves `self.reference` against the just-fetched object database
+    /// and returns its tree's `ObjectId`, so every lookup re-reads whatever
+    /// commit the ref points at *now* rather than whatever was checked out
+    /// once.
+    fn resolve_tree_id(&self, repo: &gix::Repository) -> ExternalPackageResult<gix::ObjectId> {
+        let rev = repo
+            .rev_parse_single(self.reference.as_str())
+            .map_err(|_| ExternalPackageError::GitInvalidRef {
+                url: self.url.clone(),
+                reference: self.reference.clone(),
+            })?;
+
+        // `rev_parse_single` already confirmed the ref resolves; peeling to
+        // a commit only fails if it points at something that isn't one.
+        let commit = rev
+            .object()
+            .and_then(|object| object.try_into_commit())
+            .map_err(|_| ExternalPackageError::GitInvalidRef {
+                url: self.url.clone(),
+                reference: self.reference.clone(),
+            })?;
+
+        commit
+            .tree_id()
+            .map(|id| id.detach())
+            .map_err(|err| ExternalPackageError::GitFetch {
+                url: self.url.clone(),
+                source: err.into(),
+            })
+    }
+
+    /// The tree path a package lives at within the repository.
+    fn package_tree_path(&self, spec: &PackageSpec) -> PathBuf {
+        PathBuf::from(spec.namespace.as_str())
+            .join(spec.name.as_str())
+            .join(spec.version.to_string())
+    }
+}
+
+#[async_trait]
+impl RepoProvider for GitRepoProvider {
+    /// Fetches/clones the repository as needed, then packs the tree at
+    /// `<namespace>/<name>/<version>/` into a `.tar.gz` (see [`pack_tree`]).
+    async fn fetch_archive(&self, spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+        let checkout_dir = self.checkout.lock().await;
+
+        let (repo, tree_id) = self.sync_tree(&checkout_dir)?;
+        let tree = repo.find_object(tree_id).and_then(|object| object.try_into_tree()).map_err(|err| {
+            ExternalPackageError::GitFetch {
+                url: self.url.clone(),
+                source: err.into(),
+            }
+        })?;
+
+        let package_path = self.package_tree_path(spec);
+        let subtree = find_subtree(&tree, &package_path).ok_or_else(|| {
+            ExternalPackageError::Other(anyhow::anyhow!(
+                "package {spec} not found at {} in git repository {}",
+                package_path.display(),
+                self.url
+            ))
+        })?;
+
+        pack_tree(&subtree)
+            .map_err(|err| err.context(format!("could not pack git package {spec}")))
+            .map_err(Into::into)
+    }
+
+    fn expected_digest(&self, _spec: &PackageSpec) -> Option<String> {
+        // Commits are content-addressed, but we don't yet record a digest
+        // per package in the tree; fall back to trust-on-first-use like the
+        // local mirror provider.
+        None
+    }
+}
+
+/// Walks `path`'s components down from `tree`, returning the subtree at the
+/// end of the path, if every component along the way is itself a tree.
+fn find_subtree<'repo>(tree: &gix::Tree<'repo>, path: &Path) -> Option<gix::Tree<'repo>> {
+    let mut current = tree.clone();
+    for component in path.components() {
+        let name = component.as_os_str().to_str()?;
+        let entry = current.lookup_entry_by_path(name).ok().flatten()?;
+        current = entry.object().ok()?.try_into_tree().ok()?;
+    }
+    Some(current)
+}
+
+/// Packs every blob reachable from `tree` into a `.tar.gz`, preserving its
+/// directory structure, the same shape `RepoRetrievalDest::store_from`
+/// already knows how to unpack for remote downloads.
+fn pack_tree(tree: &gix::Tree<'_>) -> anyhow::Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    let encoder = GzEncoder::new(&mut archive, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    append_tree(tree, Path::new(""), &mut builder)?;
+    builder.into_inner()?.finish()?;
+    Ok(archive)
+}
+
+fn append_tree<W: std::io::Write>(tree: &gix::Tree<'_>, prefix: &Path, builder: &mut tar::Builder<W>) -> anyhow::Result<()> {
+    for entry in tree.iter() {
+        let entry = entry?;
+        let path = prefix.join(entry.filename().to_string());
+        let object = entry.object()?;
+
+        if let Ok(subtree) = object.clone().try_into_tree() {
+            append_tree(&subtree, &path, builder)?;
+        } else if let Ok(blob) = object.try_into_blob() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(blob.data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, &path, blob.data.as_slice())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::process::Command;
+
+    use typst::syntax::PackageSpec;
+
+    use super::super::test_support::TempDir;
+    use super::*;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).status().unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn read_file_from_archive(archive: &[u8], path: &str) -> Option<String> {
+        let decoder = flate2::read::GzDecoder::new(archive);
+        let mut tar = tar::Archive::new(decoder);
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            if entry.path().unwrap() == Path::new(path) {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).unwrap();
+                return Some(contents);
+            }
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn fetch_archive_packs_the_resolved_refs_package_tree() {
+        let origin = TempDir::new("git-origin");
+        let checkout = TempDir::new("git-checkout");
+
+        run_git(origin.path(), &["init", "-q"]);
+        run_git(origin.path(), &["config", "user.email", "test@example.com"]);
+        run_git(origin.path(), &["config", "user.name", "test"]);
+
+        let package_dir = origin.path().join("preview").join("example").join("1.0.0");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(package_dir.join("typst.toml"), b"[package]\nname = \"example\"\n").unwrap();
+
+        run_git(origin.path(), &["add", "-A"]);
+        run_git(origin.path(), &["commit", "-q", "-m", "add example package"]);
+
+        let provider =
+            GitRepoProvider::new(origin.path().to_string_lossy().into_owned(), None, checkout.path().to_path_buf());
+
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+        let archive = provider.fetch_archive(&spec).await.unwrap();
+
+        let manifest =
+            read_file_from_archive(&archive, "typst.toml").expect("typst.toml should be packed into the archive");
+        assert!(manifest.contains("name = \"example\""));
+    }
+
+    #[tokio::test]
+    async fn fetch_archive_rereads_the_ref_after_a_later_commit_moves_it() {
+        let origin = TempDir::new("git-origin-update");
+        let checkout = TempDir::new("git-checkout-update");
+
+        run_git(origin.path(), &["init", "-q"]);
+        run_git(origin.path(), &["config", "user.email", "test@example.com"]);
+        run_git(origin.path(), &["config", "user.name", "test"]);
+
+        let package_dir = origin.path().join("preview").join("example").join("1.0.0");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(package_dir.join("typst.toml"), b"version = 1\n").unwrap();
+        run_git(origin.path(), &["add", "-A"]);
+        run_git(origin.path(), &["commit", "-q", "-m", "v1"]);
+
+        let provider =
+            GitRepoProvider::new(origin.path().to_string_lossy().into_owned(), None, checkout.path().to_path_buf());
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+
+        let first = provider.fetch_archive(&spec).await.unwrap();
+        assert_eq!(read_file_from_archive(&first, "typst.toml").unwrap(), "version = 1\n");
+
+        std::fs::write(package_dir.join("typst.toml"), b"version = 2\n").unwrap();
+        run_git(origin.path(), &["add", "-A"]);
+        run_git(origin.path(), &["commit", "-q", "-m", "v2"]);
+
+        let second = provider.fetch_archive(&spec).await.unwrap();
+        assert_eq!(read_file_from_archive(&second, "typst.toml").unwrap(), "version = 2\n");
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use typst::syntax::PackageSpec;
+
+use crate::workspace::package::manager::ExternalPackageResult;
+
+use super::RepoProvider;
+
+/// Serves packages straight out of a local directory laid out like a
+/// registry mirror (`<namespace>/<name>/<version>/`), for registries
+/// configured with [`RegistrySource::LocalMirror`](super::registry::RegistrySource::LocalMirror).
+#[derive(Debug, Clone)]
+pub struct LocalMirrorRepoProvider {
+    root: PathBuf,
+}
+
+impl LocalMirrorRepoProvider {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn package_dir(&self, spec: &PackageSpec) -> PathBuf {
+        self.root
+            .join(spec.namespace.as_str())
+            .join(spec.name.as_str())
+            .join(spec.version.to_string())
+    }
+}
+
+#[async_trait]
+impl RepoProvider for LocalMirrorRepoProvider {
+    /// Packs the package's directory on the mirror into a `.tar.gz` (see
+    /// [`pack_dir`]).
+    async fn fetch_archive(&self, spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+        let dir = self.package_dir(spec);
+        pack_dir(&dir)
+            .map_err(|err| err.context(format!("could not pack local mirror package {spec}")))
+            .map_err(Into::into)
+    }
+
+    fn expected_digest(&self, _spec: &PackageSpec) -> Option<String> {
+        // Mirrors are trusted as-is; checksums are still recorded
+        // trust-on-first-use once the package lands in the cache.
+        None
+    }
+}
+
+/// Archives `dir` into a `.tar.gz`, the same shape `RepoRetrievalDest::store_from`
+/// unpacks for remote downloads.
+fn pack_dir(dir: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    let encoder = GzEncoder::new(&mut archive, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.append_dir_all(".", dir)?;
+    builder.into_inner()?.finish()?;
+    Ok(archive)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::super::test_support::TempDir;
+    use super::*;
+
+    #[test]
+    fn package_dir_is_namespace_name_version() {
+        let provider = LocalMirrorRepoProvider::new(PathBuf::from("/mirror"));
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+
+        assert_eq!(provider.package_dir(&spec), PathBuf::from("/mirror/preview/example/1.0.0"));
+    }
+
+    #[test]
+    fn pack_dir_archives_the_directorys_contents() {
+        let dir = TempDir::new("local-mirror-pack");
+        std::fs::write(dir.path().join("typst.toml"), b"[package]\nname = \"example\"\n").unwrap();
+
+        let archive = pack_dir(dir.path()).unwrap();
+
+        let decoder = flate2::read::GzDecoder::new(archive.as_slice());
+        let mut tar = tar::Archive::new(decoder);
+        // `append_dir_all(".", ..)` also emits an entry for the directory
+        // itself, so the file is looked up by name rather than assumed to
+        // come first.
+        let mut entry = tar
+            .entries()
+            .unwrap()
+            .map(Result::unwrap)
+            .find(|entry| entry.path().unwrap().file_name() == Some(std::ffi::OsStr::new("typst.toml")))
+            .expect("typst.toml should be packed into the archive");
+        let path = entry.path().unwrap().into_owned();
+        assert_eq!(path.strip_prefix(".").unwrap_or(path.as_path()), Path::new("typst.toml"));
+
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "[package]\nname = \"example\"\n");
+    }
+}
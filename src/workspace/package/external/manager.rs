@@ -1,4 +1,13 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+
 use anyhow::anyhow;
+use async_trait::async_trait;
+use ecow::EcoString;
+use futures::stream::{self, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex as AsyncMutex, RwLock, Semaphore};
 use tower_lsp::lsp_types::Url;
 use tracing::warn;
 use typst::syntax::PackageSpec;
@@ -7,30 +16,62 @@ use crate::workspace::package::manager::{ExternalPackageError, ExternalPackageRe
 use crate::workspace::package::{FullFileId, Package};
 
 use super::local::LocalProvider;
+use super::registry::{RegistryConfig, RegistryConfigKind, RegistryMap};
 use super::{ExternalPackageProvider, RepoProvider, RepoRetrievalDest};
 
-#[cfg(feature = "remote-packages")]
-type DefaultRepoProvider = Option<super::remote_repo::RemoteRepoProvider>;
-#[cfg(not(feature = "remote-packages"))]
-type DefaultRepoProvider = ();
+/// Extension of the sidecar file next to a cached package that records the
+/// SHA-256 digest of the package's unpacked files (see [`hash_package_dir`]).
+/// It is written on the first download and checked whenever the package is
+/// downloaded again; loading an already-cached package does not check it.
+const CHECKSUM_SIDECAR_EXTENSION: &str = "sha256";
+
+/// Namespace of the official package index, registered as a static default
+/// so the manager works out of the box with no user configuration.
+const PREVIEW_NAMESPACE: &str = "preview";
+
+/// Upper bound on packages downloaded at once during a [`prefetch`](ExternalPackageManager::prefetch),
+/// mirroring Fuchsia's cap on concurrent package listing requests.
+const MAX_CONCURRENT_PREFETCH: usize = 5;
 
 #[cfg(feature = "remote-packages")]
-fn get_default_repo_provider() -> DefaultRepoProvider {
-    super::remote_repo::RemoteRepoProvider::new()
-        .map_err(|err| warn!(%err, "could not get repo provider for Typst packages"))
-        .ok()
+fn get_default_repo_provider() -> Box<dyn RepoProvider> {
+    match super::remote_repo::RemoteRepoProvider::new() {
+        Ok(provider) => Box::new(provider),
+        Err(err) => {
+            warn!(%err, "could not get repo provider for Typst packages");
+            Box::new(())
+        }
+    }
 }
 #[cfg(not(feature = "remote-packages"))]
-fn get_default_repo_provider() -> DefaultRepoProvider {}
+fn get_default_repo_provider() -> Box<dyn RepoProvider> {
+    Box::new(())
+}
+
+/// The [`RegistryMap`] a manager is born with: just `@preview`, pointed at
+/// whatever [`RepoProvider`] the `remote-packages` feature gives us.
+fn default_registries() -> RegistryMap {
+    let mut registries = RegistryMap::new();
+    registries.insert(
+        EcoString::from(PREVIEW_NAMESPACE),
+        RegistryConfigKind::Static,
+        get_default_repo_provider(),
+    );
+    registries
+}
 
 #[derive(Debug)]
-pub struct ExternalPackageManager<
-    Dest: RepoRetrievalDest = LocalProvider,
-    Repo: RepoProvider = DefaultRepoProvider,
-> {
-    providers: Vec<Box<dyn ExternalPackageProvider>>,
+pub struct ExternalPackageManager<Dest: RepoRetrievalDest = LocalProvider> {
+    /// Locked only briefly by [`export_vendor`](Self::export_vendor)'s final
+    /// registration step, rather than needing `&mut self` for its whole
+    /// network-bound run.
+    providers: AsyncMutex<Vec<Box<dyn ExternalPackageProvider>>>,
     cache: Option<Dest>,
-    repo: Repo,
+    registries: RwLock<RegistryMap>,
+    /// Per-spec locks held for the duration of a [`prefetch`](Self::prefetch)
+    /// download, so overlapping requests for the same spec share one
+    /// download instead of racing.
+    in_flight_downloads: AsyncMutex<HashMap<PackageSpec, Arc<AsyncMutex<()>>>>,
 }
 
 impl ExternalPackageManager {
@@ -67,21 +108,31 @@ impl ExternalPackageManager {
         .collect();
 
         Self {
-            providers,
+            providers: AsyncMutex::new(providers),
             cache,
-            repo: get_default_repo_provider(),
+            registries: RwLock::new(default_registries()),
+            in_flight_downloads: AsyncMutex::new(HashMap::new()),
         }
     }
 }
 
-impl<Dest: RepoRetrievalDest, Repo: RepoProvider> ExternalPackageManager<Dest, Repo> {
-    fn providers(&self) -> impl Iterator<Item = &dyn ExternalPackageProvider> {
-        self.providers.iter().map(Box::as_ref)
+impl<Dest: RepoRetrievalDest> ExternalPackageManager<Dest> {
+    /// Layers the registries declared in the user's LSP settings on top of
+    /// the static defaults, each taking over its namespace from whatever
+    /// was registered there before (static default or earlier config).
+    pub async fn configure_registries(&self, configs: impl IntoIterator<Item = RegistryConfig>) {
+        let mut registries = self.registries.write().await;
+        for config in configs {
+            registries.insert_config(config, RegistryConfigKind::Dynamic);
+        }
     }
 
     /// Gets the package for the spec, downloading it if needed
     pub async fn package(&self, spec: &PackageSpec) -> ExternalPackageResult<Package> {
-        let provider = self.providers().find_map(|provider| provider.package(spec));
+        let provider = {
+            let providers = self.providers.lock().await;
+            providers.iter().find_map(|provider| provider.package(spec))
+        };
 
         match provider {
             Some(provider) => Ok(provider),
@@ -89,18 +140,487 @@ impl<Dest: RepoRetrievalDest, Repo: RepoProvider> ExternalPackageManager<Dest, R
         }
     }
 
-    pub fn full_id(&self, uri: &Url) -> Option<FullFileId> {
-        self.providers().find_map(|provider| provider.full_id(uri))
+    pub async fn full_id(&self, uri: &Url) -> Option<FullFileId> {
+        let providers = self.providers.lock().await;
+        providers.iter().find_map(|provider| provider.full_id(uri))
+    }
+
+    /// Downloads `specs` and their transitive dependencies in the
+    /// background, so that typechecking a document with a deep dependency
+    /// tree doesn't have to fetch each package serially on demand.
+    ///
+    /// Concurrency is bounded by [`MAX_CONCURRENT_PREFETCH`], and two
+    /// requests for the same spec that overlap in time share a single
+    /// download rather than racing each other.
+    pub async fn prefetch(&self, specs: impl IntoIterator<Item = PackageSpec>) {
+        let semaphore = Semaphore::new(MAX_CONCURRENT_PREFETCH);
+        let seen = StdMutex::new(HashSet::new());
+
+        let mut queue: Vec<PackageSpec> = specs.into_iter().collect();
+        queue.retain(|spec| seen.lock().unwrap().insert(spec.clone()));
+
+        while !queue.is_empty() {
+            let batch = std::mem::take(&mut queue);
+
+            let discovered = stream::iter(batch)
+                .map(|spec| {
+                    let semaphore = &semaphore;
+                    async move {
+                        let _permit = semaphore.acquire().await.expect("semaphore is never closed");
+                        self.prefetch_one(spec).await
+                    }
+                })
+                .buffer_unordered(MAX_CONCURRENT_PREFETCH)
+                .collect::<Vec<_>>()
+                .await;
+
+            let mut seen = seen.lock().unwrap();
+            for dep in discovered.into_iter().flatten() {
+                if seen.insert(dep.clone()) {
+                    queue.push(dep);
+                }
+            }
+        }
+    }
+
+    /// Downloads a single spec for [`prefetch`](Self::prefetch), joining an
+    /// already-running download for the same spec instead of starting a
+    /// second one, and returns the dependencies its manifest declares.
+    async fn prefetch_one(&self, spec: PackageSpec) -> Vec<PackageSpec> {
+        let lock = {
+            let mut in_flight = self.in_flight_downloads.lock().await;
+            in_flight.entry(spec.clone()).or_insert_with(|| Arc::new(AsyncMutex::new(()))).clone()
+        };
+
+        let result = {
+            let _guard = lock.lock().await;
+            self.package(&spec).await
+        };
+
+        self.in_flight_downloads.lock().await.remove(&spec);
+
+        match result {
+            Ok(package) => declared_dependencies(&package).unwrap_or_else(|err| {
+                warn!(%err, %spec, "could not read dependencies of prefetched package");
+                Vec::new()
+            }),
+            Err(err) => {
+                warn!(%err, %spec, "could not prefetch package");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Resolves (downloading as needed) every package reachable from
+    /// `specs` and copies its tree into `dest/<namespace>/<name>/<version>/`,
+    /// then registers `dest` as an additional, highest-priority provider so
+    /// future [`package`](Self::package) calls resolve it before reaching
+    /// out to any registry again.
+    pub async fn export_vendor(
+        &self,
+        specs: impl IntoIterator<Item = PackageSpec>,
+        dest: &Path,
+    ) -> ExternalPackageResult<()> {
+        let mut queue: Vec<PackageSpec> = specs.into_iter().collect();
+        let mut seen: HashSet<PackageSpec> = queue.iter().cloned().collect();
+
+        let mut i = 0;
+        while i < queue.len() {
+            let spec = queue[i].clone();
+            i += 1;
+
+            let package = self.package(&spec).await?;
+
+            let vendor_dir = vendor_package_dir(dest, &spec);
+            copy_dir_all(package.path(), &vendor_dir)
+                .map_err(|err| ExternalPackageError::Other(err.context(format!("could not vendor package {spec}"))))?;
+
+            let deps = declared_dependencies(&package).unwrap_or_else(|err| {
+                warn!(%err, %spec, "could not read dependencies of vendored package");
+                Vec::new()
+            });
+            for dep in deps {
+                if seen.insert(dep.clone()) {
+                    queue.push(dep);
+                }
+            }
+        }
+
+        let vendor_provider = Box::new(LocalProvider::new(dest.to_path_buf())) as Box<dyn ExternalPackageProvider>;
+        self.providers.lock().await.insert(0, vendor_provider);
+
+        Ok(())
     }
 
     #[tracing::instrument]
     async fn download_to_cache(&self, spec: &PackageSpec) -> ExternalPackageResult<Package> {
-        if let Some(cache) = &self.cache {
-            Ok(cache.store_from(&self.repo, spec).await?)
-        } else {
-            Err(ExternalPackageError::Other(anyhow!(
+        let Some(cache) = &self.cache else {
+            return Err(ExternalPackageError::Other(anyhow!(
                 "nowhere to download package {spec}"
-            )))
+            )));
+        };
+
+        // A read lock, so downloads from several prefetch tasks can run at
+        // the same time; only `configure_registries` needs exclusive access.
+        let registries = self.registries.read().await;
+        let repo = registries.provider_for(&spec.namespace).ok_or_else(|| {
+            ExternalPackageError::Other(anyhow!("no registry configured for namespace {}", spec.namespace))
+        })?;
+
+        // Checks the archive against a digest the registry's index already
+        // knows *before* `store_from` ever unpacks it, so a mismatch here
+        // never reaches the cache at all.
+        let verifying_repo = VerifyingRepoProvider { inner: repo };
+        let package = cache.store_from(&verifying_repo, spec).await?;
+
+        // No index-known digest, or it matched: fall back to comparing
+        // against (and recording) a trust-on-first-use sidecar. If *this*
+        // disagrees with a previous download, the corrupted package must
+        // not stay committed in the cache for later `package()` calls to
+        // hand back unverified.
+        if let Err(err) = self.verify_against_sidecar(spec, &package) {
+            if let Err(cleanup_err) = std::fs::remove_dir_all(package.path()) {
+                warn!(
+                    %cleanup_err,
+                    path = %package.path().display(),
+                    "could not remove corrupted package from cache"
+                );
+            }
+            return Err(err);
+        }
+
+        Ok(package)
+    }
+
+    /// Checks the freshly-downloaded `package` against the digest we recorded
+    /// for it on a previous download (trust-on-first-use), recording one if
+    /// this is the first time we've seen it.
+    fn verify_against_sidecar(&self, spec: &PackageSpec, package: &Package) -> ExternalPackageResult<()> {
+        let actual = hash_package_dir(package.path())
+            .map_err(|err| ExternalPackageError::Other(err.context("could not hash package")))?;
+
+        let sidecar = checksum_sidecar_path(package.path());
+        let expected = std::fs::read_to_string(&sidecar).ok().map(|s| s.trim().to_owned());
+
+        match expected {
+            Some(expected) if expected != actual => Err(ExternalPackageError::ChecksumMismatch {
+                spec: spec.clone(),
+                expected,
+                actual,
+            }),
+            _ => {
+                if let Err(err) = std::fs::write(&sidecar, &actual) {
+                    warn!(%err, path = %sidecar.display(), "could not write checksum sidecar");
+                }
+                Ok(())
+            }
         }
     }
 }
+
+/// Wraps a [`RepoProvider`] so the archive it fetches is checked against
+/// the digest the registry's index already knows about *before*
+/// [`RepoRetrievalDest::store_from`] ever unpacks it into the cache —
+/// refusing to commit a tampered or corrupted download instead of catching
+/// it only after the fact.
+struct VerifyingRepoProvider<'a> {
+    inner: &'a dyn RepoProvider,
+}
+
+#[async_trait]
+impl RepoProvider for VerifyingRepoProvider<'_> {
+    async fn fetch_archive(&self, spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+        let archive = self.inner.fetch_archive(spec).await?;
+
+        if let Some(expected) = self.inner.expected_digest(spec) {
+            let actual = format!("{:x}", Sha256::digest(&archive));
+            if expected != actual {
+                return Err(ExternalPackageError::ChecksumMismatch {
+                    spec: spec.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(archive)
+    }
+
+    fn expected_digest(&self, spec: &PackageSpec) -> Option<String> {
+        self.inner.expected_digest(spec)
+    }
+}
+
+/// Path of the `<version>.sha256` sidecar that sits next to a cached
+/// package. `package_dir`'s file name is a version number like `1.0.0`, so
+/// this can't use [`Path::with_extension`]: it replaces everything after
+/// the *last* dot, which would collapse `1.0.0` and `1.0.5` onto the same
+/// sidecar path.
+fn checksum_sidecar_path(package_dir: &std::path::Path) -> PathBuf {
+    let mut name = package_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(CHECKSUM_SIDECAR_EXTENSION);
+    package_dir.with_file_name(name)
+}
+
+/// Hashes every file under `dir` (sorted by path, for a stable digest) with
+/// SHA-256, feeding each file's path relative to `dir` into the hash ahead
+/// of its contents so that renaming, moving, or splitting files changes the
+/// digest even when the multiset of file bytes doesn't.
+fn hash_package_dir(dir: &std::path::Path) -> anyhow::Result<String> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let relpath = file.strip_prefix(dir).unwrap_or(&file);
+        hasher.update(relpath.to_string_lossy().as_bytes());
+        hasher.update(std::fs::read(&file)?);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where [`ExternalPackageManager::export_vendor`] copies a resolved
+/// package's tree to under `dest`, laid out the same way a registry mirror
+/// is: `<namespace>/<name>/<version>/`.
+fn vendor_package_dir(dest: &Path, spec: &PackageSpec) -> PathBuf {
+    dest.join(spec.namespace.as_str()).join(spec.name.as_str()).join(spec.version.to_string())
+}
+
+/// The packages a downloaded package's `typst.toml` declares as
+/// dependencies, so [`ExternalPackageManager::prefetch`] can walk the whole
+/// dependency graph instead of stopping at the first hop.
+fn declared_dependencies(package: &Package) -> anyhow::Result<Vec<PackageSpec>> {
+    let manifest = std::fs::read_to_string(package.path().join("typst.toml"))?;
+    parse_declared_dependencies(&manifest)
+}
+
+/// The `[dependencies]` table of a `typst.toml` manifest, parsed into the
+/// specs it names. Split out from [`declared_dependencies`] so the parsing
+/// itself can be unit tested without a [`Package`] to read one off disk.
+fn parse_declared_dependencies(manifest: &str) -> anyhow::Result<Vec<PackageSpec>> {
+    let manifest: toml::Value = manifest.parse()?;
+
+    let deps = manifest
+        .get("dependencies")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, value)| value.as_str())
+        .filter_map(|spec| spec.parse::<PackageSpec>().ok())
+        .collect();
+
+    Ok(deps)
+}
+
+/// Recursively copies every file under `src` into `dst`, creating
+/// directories as needed, for [`ExternalPackageManager::export_vendor`].
+fn copy_dir_all(src: &std::path::Path, dst: &std::path::Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let dst_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst_path)?;
+        } else {
+            std::fs::copy(entry.path(), dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_files(dir: &std::path::Path, out: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::super::test_support::TempDir;
+    use super::*;
+
+    /// A [`RepoProvider`] that always hands back an empty archive, for tests
+    /// that only care about what [`ExternalPackageManager`] does around the
+    /// fetch, not what a real registry would serve.
+    #[derive(Debug)]
+    struct EmptyArchiveRepo;
+
+    #[async_trait]
+    impl RepoProvider for EmptyArchiveRepo {
+        async fn fetch_archive(&self, _spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+            Ok(Vec::new())
+        }
+
+        fn expected_digest(&self, _spec: &PackageSpec) -> Option<String> {
+            None
+        }
+    }
+
+    /// A [`RepoRetrievalDest`] that never actually stores anything, but
+    /// tracks how many [`store_from`](RepoRetrievalDest::store_from) calls
+    /// are in flight at once, so a test can assert two downloads of the same
+    /// spec never overlap.
+    #[derive(Debug, Clone, Default)]
+    struct TrackingDest {
+        in_flight: std::sync::Arc<AtomicUsize>,
+        max_in_flight: std::sync::Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl RepoRetrievalDest for TrackingDest {
+        async fn store_from(&self, _repo: &dyn RepoProvider, _spec: &PackageSpec) -> ExternalPackageResult<Package> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Err(ExternalPackageError::Other(anyhow!("TrackingDest never actually stores anything")))
+        }
+    }
+
+    #[tokio::test]
+    async fn prefetch_never_downloads_the_same_spec_concurrently() {
+        let dest = TrackingDest::default();
+
+        let mut registries = RegistryMap::new();
+        registries.insert(EcoString::from("preview"), RegistryConfigKind::Static, Box::new(EmptyArchiveRepo));
+
+        let manager = ExternalPackageManager {
+            providers: AsyncMutex::new(Vec::new()),
+            cache: Some(dest.clone()),
+            registries: RwLock::new(registries),
+            in_flight_downloads: AsyncMutex::new(HashMap::new()),
+        };
+
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+        tokio::join!(manager.prefetch([spec.clone()]), manager.prefetch([spec.clone()]));
+
+        assert_eq!(
+            dest.max_in_flight.load(Ordering::SeqCst),
+            1,
+            "two overlapping prefetches of the same spec raced instead of sharing one download"
+        );
+    }
+
+    #[test]
+    fn hash_package_dir_is_stable_regardless_of_read_dir_order() {
+        let dir = TempDir::new("hash-stable");
+        std::fs::write(dir.path().join("b.txt"), b"second").unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"first").unwrap();
+
+        let first = hash_package_dir(dir.path()).unwrap();
+        let second = hash_package_dir(dir.path()).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn hash_package_dir_changes_when_a_file_is_renamed() {
+        let dir = TempDir::new("hash-renamed");
+        std::fs::write(dir.path().join("a.txt"), b"first").unwrap();
+        let before = hash_package_dir(dir.path()).unwrap();
+
+        std::fs::rename(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+        let after = hash_package_dir(dir.path()).unwrap();
+
+        assert_ne!(before, after, "renaming a file must change the digest even though its bytes didn't");
+    }
+
+    #[test]
+    fn checksum_sidecar_path_distinguishes_dotted_version_numbers() {
+        let packages = Path::new("/cache/preview/example");
+        let sidecar_1_0_0 = checksum_sidecar_path(&packages.join("1.0.0"));
+        let sidecar_1_0_5 = checksum_sidecar_path(&packages.join("1.0.5"));
+
+        assert_ne!(
+            sidecar_1_0_0, sidecar_1_0_5,
+            "with_extension would collapse both onto .../1.0.sha256"
+        );
+        assert_eq!(sidecar_1_0_0, Path::new("/cache/preview/example/1.0.0.sha256"));
+    }
+
+    #[test]
+    fn vendor_package_dir_is_laid_out_like_a_registry_mirror() {
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+        let dir = vendor_package_dir(Path::new("/vendor"), &spec);
+        assert_eq!(dir, Path::new("/vendor/preview/example/1.0.0"));
+    }
+
+    #[test]
+    fn hash_package_dir_changes_with_file_contents() {
+        let dir = TempDir::new("hash-changes");
+        std::fs::write(dir.path().join("a.txt"), b"first").unwrap();
+        let before = hash_package_dir(dir.path()).unwrap();
+
+        std::fs::write(dir.path().join("a.txt"), b"tampered").unwrap();
+        let after = hash_package_dir(dir.path()).unwrap();
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn parse_declared_dependencies_reads_dependency_specs() {
+        let manifest = r#"
+            [package]
+            name = "example"
+
+            [dependencies]
+            other = "@preview/other:1.0.0"
+        "#;
+
+        let deps = parse_declared_dependencies(manifest).unwrap();
+        assert_eq!(deps, vec!["@preview/other:1.0.0".parse::<PackageSpec>().unwrap()]);
+    }
+
+    #[test]
+    fn parse_declared_dependencies_ignores_malformed_entries() {
+        let manifest = r#"
+            [dependencies]
+            bad = "not-a-valid-spec"
+        "#;
+
+        let deps = parse_declared_dependencies(manifest).unwrap();
+        assert!(deps.is_empty());
+    }
+
+    #[test]
+    fn parse_declared_dependencies_empty_without_dependencies_table() {
+        let manifest = r#"
+            [package]
+            name = "example"
+        "#;
+
+        let deps = parse_declared_dependencies(manifest).unwrap();
+        assert!(deps.is_empty());
+    }
+
+    #[test]
+    fn copy_dir_all_mirrors_nested_structure() {
+        let src = TempDir::new("copy-src");
+        let dst = TempDir::new("copy-dst");
+
+        std::fs::write(src.path().join("top.txt"), b"top").unwrap();
+        std::fs::create_dir_all(src.path().join("nested")).unwrap();
+        std::fs::write(src.path().join("nested").join("inner.txt"), b"inner").unwrap();
+
+        copy_dir_all(src.path(), dst.path()).unwrap();
+
+        assert_eq!(std::fs::read(dst.path().join("top.txt")).unwrap(), b"top");
+        assert_eq!(
+            std::fs::read(dst.path().join("nested").join("inner.txt")).unwrap(),
+            b"inner"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use ecow::EcoString;
+use serde::Deserialize;
+use tracing::warn;
+
+use super::git_repo::GitRepoProvider;
+use super::local_mirror::LocalMirrorRepoProvider;
+use super::RepoProvider;
+
+#[cfg(feature = "remote-packages")]
+fn parse_tuf_root_keys(keys: &[String]) -> anyhow::Result<Vec<tuf::crypto::PublicKey>> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    keys.iter()
+        .map(|key| {
+            let bytes = STANDARD.decode(key.trim())?;
+            Ok(tuf::crypto::PublicKey::from_ed25519(bytes)?)
+        })
+        .collect()
+}
+
+/// Where a configured registry's packages are fetched from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RegistrySource {
+    /// A base URL serving packages the same way the official index does.
+    Remote {
+        base_url: String,
+        /// Base64-encoded ed25519 TUF root keys pinned for this registry.
+        /// When set, the registry's signed `root`/`targets` metadata is
+        /// verified before any archive it describes is trusted; when
+        /// absent, integrity falls back to trust-on-first-use. Keys that
+        /// fail to decode are ignored with a warning, which leaves the
+        /// registry on trust-on-first-use as well.
+        #[serde(default)]
+        tuf_root_keys: Vec<String>,
+    },
+    /// A local directory laid out as `<namespace>/<name>/<version>/`, e.g. a
+    /// shared network mirror or an offline vendor directory.
+    LocalMirror { path: PathBuf },
+    /// A git repository holding packages at `<namespace>/<name>/<version>/`,
+    /// fetched into `checkout` and updated in place on later lookups.
+    Git {
+        url: String,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+        checkout: PathBuf,
+    },
+}
+
+impl RegistrySource {
+    /// Builds the [`RepoProvider`] that knows how to actually fetch
+    /// packages from this source.
+    pub fn into_provider(self) -> Box<dyn RepoProvider> {
+        match self {
+            #[cfg(feature = "remote-packages")]
+            RegistrySource::Remote { base_url, tuf_root_keys } => {
+                let mut provider = super::remote_repo::RemoteRepoProvider::with_base_url(base_url);
+                if !tuf_root_keys.is_empty() {
+                    match parse_tuf_root_keys(&tuf_root_keys) {
+                        Ok(root_keys) => provider = provider.with_tuf_root(root_keys),
+                        Err(err) => warn!(%err, "ignoring invalid tuf_root_keys for registry"),
+                    }
+                }
+                Box::new(provider)
+            }
+            #[cfg(not(feature = "remote-packages"))]
+            RegistrySource::Remote { .. } => Box::new(()),
+            RegistrySource::LocalMirror { path } => Box::new(LocalMirrorRepoProvider::new(path)),
+            RegistrySource::Git { url, reference, checkout } => {
+                Box::new(GitRepoProvider::new(url, reference, checkout))
+            }
+        }
+    }
+}
+
+/// One registry the user has declared in their LSP settings, mapping a
+/// package namespace (the `"preview"` in `@preview/example:1.0.0`) to where
+/// its packages live.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryConfig {
+    pub namespace: EcoString,
+    pub source: RegistrySource,
+}
+
+/// Distinguishes registries we ship by default from ones the user added,
+/// mirroring Fuchsia's `RepositoryManager` split between *static* and
+/// *dynamic* repo configs: dynamic configs are layered on top of, and take
+/// priority over, static ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegistryConfigKind {
+    Static,
+    Dynamic,
+}
+
+/// The registries an [`ExternalPackageManager`](super::manager::ExternalPackageManager)
+/// knows about, keyed by namespace, with at most one [`RepoProvider`] per
+/// namespace.
+///
+/// Static (shipped) configs are inserted first, and any dynamic (user-added)
+/// config for the same namespace then takes over, so a user can point
+/// `@preview` at an internal mirror without losing the rest of the static
+/// defaults.
+#[derive(Debug, Default)]
+pub struct RegistryMap {
+    entries: BTreeMap<EcoString, (RegistryConfigKind, Box<dyn RepoProvider>)>,
+}
+
+impl RegistryMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `provider` as the handler for `namespace`, unless a
+    /// strictly higher priority entry is already registered for it (a
+    /// static default never displaces an existing dynamic override, while a
+    /// later entry of equal priority replaces an earlier one).
+    pub fn insert(&mut self, namespace: EcoString, kind: RegistryConfigKind, provider: Box<dyn RepoProvider>) {
+        let displaces_existing = match self.entries.get(&namespace) {
+            Some((existing_kind, _)) => kind >= *existing_kind,
+            None => true,
+        };
+
+        if displaces_existing {
+            self.entries.insert(namespace, (kind, provider));
+        }
+    }
+
+    /// Registers a user-declared [`RegistryConfig`], building its provider
+    /// from the [`RegistrySource`] it carries.
+    pub fn insert_config(&mut self, config: RegistryConfig, kind: RegistryConfigKind) {
+        self.insert(config.namespace, kind, config.source.into_provider());
+    }
+
+    /// The provider configured for `namespace`, if any.
+    pub fn provider_for(&self, namespace: &EcoString) -> Option<&dyn RepoProvider> {
+        self.entries.get(namespace).map(|(_, provider)| provider.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use typst::syntax::PackageSpec;
+
+    use crate::workspace::package::manager::ExternalPackageResult;
+
+    use super::*;
+
+    /// A [`RepoProvider`] that only exists to be distinguishable by identity
+    /// in these tests.
+    #[derive(Debug)]
+    struct MarkerProvider(&'static str);
+
+    #[async_trait]
+    impl RepoProvider for MarkerProvider {
+        async fn fetch_archive(&self, _spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        fn expected_digest(&self, _spec: &PackageSpec) -> Option<String> {
+            None
+        }
+    }
+
+    fn marker_of(provider: Option<&dyn RepoProvider>) -> String {
+        format!("{provider:?}")
+    }
+
+    #[test]
+    fn dynamic_displaces_static_for_same_namespace() {
+        let mut map = RegistryMap::new();
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Static, Box::new(MarkerProvider("static")));
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Dynamic, Box::new(MarkerProvider("dynamic")));
+
+        let winner = marker_of(map.provider_for(&EcoString::from("preview")));
+        assert!(winner.contains("dynamic"), "expected dynamic provider, got {winner}");
+    }
+
+    #[test]
+    fn static_does_not_displace_existing_dynamic() {
+        let mut map = RegistryMap::new();
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Dynamic, Box::new(MarkerProvider("dynamic")));
+        // Inserting a static config for the same namespace afterwards must
+        // not overwrite the dynamic (user-configured) one.
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Static, Box::new(MarkerProvider("static")));
+
+        let winner = marker_of(map.provider_for(&EcoString::from("preview")));
+        assert!(winner.contains("dynamic"), "expected dynamic provider, got {winner}");
+    }
+
+    #[test]
+    fn later_dynamic_displaces_earlier_dynamic() {
+        let mut map = RegistryMap::new();
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Dynamic, Box::new(MarkerProvider("first")));
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Dynamic, Box::new(MarkerProvider("second")));
+
+        let winner = marker_of(map.provider_for(&EcoString::from("preview")));
+        assert!(winner.contains("second"), "expected second provider, got {winner}");
+    }
+
+    #[test]
+    fn unconfigured_namespace_has_no_provider() {
+        let map = RegistryMap::new();
+        assert!(map.provider_for(&EcoString::from("preview")).is_none());
+    }
+
+    #[test]
+    fn distinct_namespaces_do_not_interfere() {
+        let mut map = RegistryMap::new();
+        map.insert(EcoString::from("preview"), RegistryConfigKind::Static, Box::new(MarkerProvider("preview")));
+        map.insert(EcoString::from("internal"), RegistryConfigKind::Dynamic, Box::new(MarkerProvider("internal")));
+
+        assert!(map.provider_for(&EcoString::from("preview")).is_some());
+        assert!(map.provider_for(&EcoString::from("internal")).is_some());
+        assert!(map.provider_for(&EcoString::from("other")).is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use tokio::sync::OnceCell;
+use tuf::crypto::PublicKey;
+use typst::syntax::PackageSpec;
+
+use async_trait::async_trait;
+
+use crate::workspace::package::manager::{ExternalPackageError, ExternalPackageResult};
+
+use super::tuf::TufVerifier;
+use super::RepoProvider;
+
+/// Base URL of the official Typst package index, served the same way any
+/// other `remote-packages` registry is.
+const DEFAULT_BASE_URL: &str = "https://packages.typst.org";
+
+/// Fetches packages over HTTP from a registry laid out like the official
+/// index: `<base_url>/<namespace>/<name>-<version>.tar.gz`.
+///
+/// When built [`with_tuf_root`](Self::with_tuf_root), archives are only
+/// trusted once their signed TUF metadata has verified a matching
+/// length and hash, giving private registries a tamper-evident install
+/// path; without it, integrity relies solely on the trust-on-first-use
+/// checksum sidecar [`ExternalPackageManager`](super::manager::ExternalPackageManager)
+/// already keeps.
+#[derive(Debug)]
+pub struct RemoteRepoProvider {
+    base_url: String,
+    tuf_root: Option<Vec<PublicKey>>,
+    /// Built lazily on first fetch, since standing up a `TufVerifier`
+    /// touches the filesystem and network and this provider is otherwise
+    /// constructed synchronously alongside the rest of the registry map.
+    tuf: OnceCell<TufVerifier>,
+}
+
+impl RemoteRepoProvider {
+    /// The default provider for the official `@preview` index.
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self::with_base_url(DEFAULT_BASE_URL.to_owned()))
+    }
+
+    pub fn with_base_url(base_url: String) -> Self {
+        Self {
+            base_url,
+            tuf_root: None,
+            tuf: OnceCell::new(),
+        }
+    }
+
+    /// Authenticates this registry's metadata with TUF, pinned to
+    /// `root_keys`.
+    pub fn with_tuf_root(mut self, root_keys: Vec<PublicKey>) -> Self {
+        self.tuf_root = Some(root_keys);
+        self
+    }
+
+    fn archive_url(&self, spec: &PackageSpec) -> String {
+        format!("{}/{}/{}-{}.tar.gz", self.base_url, spec.namespace, spec.name, spec.version)
+    }
+
+    /// The `TufVerifier` for this registry, built (and its local metadata
+    /// cache created) on first use. `None` when no root keys were pinned,
+    /// i.e. this registry isn't authenticated with TUF.
+    async fn tuf(&self, spec: &PackageSpec) -> ExternalPackageResult<Option<&TufVerifier>> {
+        let Some(root_keys) = &self.tuf_root else {
+            return Ok(None);
+        };
+
+        let verifier = self
+            .tuf
+            .get_or_try_init(|| async {
+                let metadata_base_url = format!("{}/tuf", self.base_url);
+                TufVerifier::new(&metadata_base_url, root_keys.clone(), tuf_cache_dir(&self.base_url)).await
+            })
+            .await
+            .map_err(|err| ExternalPackageError::MetadataVerification {
+                spec: spec.clone(),
+                source: err,
+            })?;
+
+        Ok(Some(verifier))
+    }
+}
+
+#[async_trait]
+impl RepoProvider for RemoteRepoProvider {
+    async fn fetch_archive(&self, spec: &PackageSpec) -> ExternalPackageResult<Vec<u8>> {
+        let tuf = self.tuf(spec).await?;
+        let target = match tuf {
+            Some(tuf) => Some(tuf.verified_target(spec).await?),
+            None => None,
+        };
+
+        let response = reqwest::get(self.archive_url(spec)).await.and_then(|resp| resp.error_for_status());
+        let archive = response
+            .map_err(|err| ExternalPackageError::Other(anyhow::Error::new(err).context(format!("could not download package {spec}"))))?
+            .bytes()
+            .await
+            .map_err(|err| ExternalPackageError::Other(err.into()))?
+            .to_vec();
+
+        if let (Some(tuf), Some(target)) = (tuf, &target) {
+            tuf.verify_archive(spec, target, &archive)?;
+        }
+
+        Ok(archive)
+    }
+
+    fn expected_digest(&self, _spec: &PackageSpec) -> Option<String> {
+        // No per-package digest is known ahead of the download. With TUF
+        // roots pinned, `fetch_archive` has already checked the archive
+        // against its signed description; either way the manager still
+        // records and checks its trust-on-first-use checksum sidecar.
+        None
+    }
+}
+
+/// Where a registry's verified TUF metadata is cached between runs, namespaced
+/// by base URL so two registries don't clobber each other's trust state.
+fn tuf_cache_dir(base_url: &str) -> std::path::PathBuf {
+    let dir = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    dir.join("typst/tuf").join(sanitize_for_path(base_url))
+}
+
+fn sanitize_for_path(s: &str) -> String {
+    s.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_url_points_at_the_namespaced_tarball() {
+        let provider = RemoteRepoProvider::with_base_url("https://example.com".to_owned());
+        let spec: PackageSpec = "@preview/example:1.0.0".parse().unwrap();
+
+        assert_eq!(provider.archive_url(&spec), "https://example.com/preview/example-1.0.0.tar.gz");
+    }
+
+    #[test]
+    fn sanitize_for_path_replaces_non_alphanumerics() {
+        assert_eq!(sanitize_for_path("https://example.com:8080"), "https___example_com_8080");
+    }
+
+    #[test]
+    fn tuf_cache_dir_is_namespaced_by_sanitized_base_url() {
+        let dir = tuf_cache_dir("https://example.com");
+        assert!(dir.ends_with("typst/tuf/https___example_com"));
+    }
+}
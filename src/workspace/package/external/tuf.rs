@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+use tokio::sync::Mutex;
+use tuf::client::{Client, Config};
+use tuf::crypto::PublicKey;
+use tuf::metadata::{MetadataVersion, TargetDescription, TargetPath};
+use tuf::repository::{FilesystemRepository, HttpRepositoryBuilder};
+use typst::syntax::PackageSpec;
+
+use crate::workspace::package::manager::{ExternalPackageError, ExternalPackageResult};
+
+/// Authenticates a registry's package metadata with [The Update
+/// Framework](https://theupdateframework.io/), driving a `tuf::client::Client`
+/// over signed `root`/`targets` metadata before trusting anything it serves.
+///
+/// The client's local metadata cache lives under `cache_dir`, so a stale or
+/// rolled-back `targets.json` served by a compromised mirror is rejected
+/// against the last version we trusted, not just against what the mirror
+/// claims is current.
+pub struct TufVerifier {
+    client: Mutex<Client<FilesystemRepository<Config>, HttpRepositoryBuilder<reqwest::Client, Config>, Config>>,
+}
+
+impl std::fmt::Debug for TufVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TufVerifier").finish_non_exhaustive()
+    }
+}
+
+impl TufVerifier {
+    /// Builds a verifier pinned to `root_keys`, fetching remote metadata
+    /// over HTTP from `metadata_base_url` and caching verified metadata
+    /// under `cache_dir` between runs.
+    pub async fn new(
+        metadata_base_url: &str,
+        root_keys: Vec<PublicKey>,
+        cache_dir: PathBuf,
+    ) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&cache_dir)?;
+
+        let local = FilesystemRepository::new(cache_dir)?;
+        let remote = HttpRepositoryBuilder::new(metadata_base_url.parse()?, reqwest::Client::new()).build();
+
+        let config = Config::default();
+        let client = Client::with_trusted_root_keys(
+            config,
+            MetadataVersion::Number(1),
+            1,
+            root_keys.iter().collect(),
+            local,
+            remote,
+        )
+        .await?;
+
+        Ok(Self { client: Mutex::new(client) })
+    }
+
+    /// Refreshes and verifies the signed `root`/`targets` metadata, then
+    /// returns the `TargetDescription` (length + hash) for the archive this
+    /// `spec` resolves to, failing closed if the metadata or the target
+    /// entry can't be verified.
+    pub async fn verified_target(&self, spec: &PackageSpec) -> ExternalPackageResult<TargetDescription> {
+        let mut client = self.client.lock().await;
+
+        client.update().await.map_err(|err| ExternalPackageError::MetadataVerification {
+            spec: spec.clone(),
+            source: err.into(),
+        })?;
+
+        let target_path = TargetPath::new(target_name(spec)).map_err(|err| {
+            ExternalPackageError::MetadataVerification {
+                spec: spec.clone(),
+                source: err.into(),
+            }
+        })?;
+
+        client
+            .fetch_target_description(&target_path)
+            .await
+            .map_err(|err| ExternalPackageError::MetadataVerification {
+                spec: spec.clone(),
+                source: err.into(),
+            })
+    }
+
+    /// Checks `archive` against `description`'s signed length and hash,
+    /// refusing to hand back bytes the signed metadata didn't vouch for.
+    pub fn verify_archive(
+        &self,
+        spec: &PackageSpec,
+        description: &TargetDescription,
+        archive: &[u8],
+    ) -> ExternalPackageResult<()> {
+        check_archive_against_description(description, archive)
+            .map_err(|source| ExternalPackageError::MetadataVerification { spec: spec.clone(), source })
+    }
+}
+
+/// Name a package's archive is published under in the signed `targets`
+/// metadata, e.g. `preview/example-1.0.0.tar.gz`.
+fn target_name(spec: &PackageSpec) -> String {
+    format!("{}/{}-{}.tar.gz", spec.namespace, spec.name, spec.version)
+}
+
+/// The actual length/hash check behind [`TufVerifier::verify_archive`],
+/// split out so it can be unit tested without a live TUF client.
+fn check_archive_against_description(description: &TargetDescription, archive: &[u8]) -> anyhow::Result<()> {
+    if archive.len() as u64 != description.length() {
+        anyhow::bail!(
+            "archive length {} does not match signed length {}",
+            archive.len(),
+            description.length()
+        );
+    }
+
+    description
+        .hashes()
+        .values()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("signed target has no hashes to check against"))?
+        .read_and_verify(&mut std::io::Cursor::new(archive))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use tuf::crypto::HashAlgorithm;
+
+    use super::*;
+
+    fn description_for(archive: &[u8]) -> TargetDescription {
+        TargetDescription::from_reader(archive, &[HashAlgorithm::Sha256]).unwrap()
+    }
+
+    #[test]
+    fn accepts_an_archive_matching_its_signed_description() {
+        let archive = b"example package contents";
+        let description = description_for(archive);
+
+        assert!(check_archive_against_description(&description, archive).is_ok());
+    }
+
+    #[test]
+    fn rejects_an_archive_with_different_contents_than_signed() {
+        let description = description_for(b"example package contents");
+
+        // Same length as the signed archive, so only the hash check can
+        // reject it.
+        assert!(check_archive_against_description(&description, b"EXAMPLE package contents").is_err());
+    }
+
+    #[test]
+    fn rejects_an_archive_shorter_than_its_signed_length() {
+        let description = description_for(b"example package contents");
+
+        assert!(check_archive_against_description(&description, b"short").is_err());
+    }
+}
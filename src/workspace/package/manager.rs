@@ -0,0 +1,49 @@
+use typst::syntax::PackageSpec;
+
+/// Errors that can occur while resolving an external package.
+#[derive(Debug, thiserror::Error)]
+pub enum ExternalPackageError {
+    /// The downloaded archive did not match the digest we expected for it.
+    #[error("checksum mismatch for package {spec}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        spec: PackageSpec,
+        expected: String,
+        actual: String,
+    },
+
+    /// Could not reach or clone the configured git remote at all.
+    #[error("could not connect to git repository {url}")]
+    GitConnect {
+        url: String,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// The repository has no `HEAD`, or the configured ref doesn't resolve
+    /// to a commit.
+    #[error("git repository {url} has no valid ref {reference}")]
+    GitInvalidRef { url: String, reference: String },
+
+    /// Connected fine, but the fetch of new commits/refs failed.
+    #[error("could not fetch from git repository {url}")]
+    GitFetch {
+        url: String,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// TUF-signed metadata for a registry namespace didn't check out: the
+    /// signatures didn't verify, the metadata was stale/rolled back, or the
+    /// downloaded archive didn't match its signed `TargetDescription`.
+    #[error("could not verify signed metadata for package {spec}")]
+    MetadataVerification {
+        spec: PackageSpec,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type ExternalPackageResult<T> = Result<T, ExternalPackageError>;